@@ -0,0 +1 @@
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS update_count INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS failed_at TIMESTAMPTZ;
@@ -0,0 +1,2 @@
+-- A runtime override of `time_to_remind`, set via the admin API.
+ALTER TABLE app_state ADD COLUMN IF NOT EXISTS time_to_remind_ms BIGINT;
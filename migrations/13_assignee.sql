@@ -0,0 +1,2 @@
+-- The issue's current assignee, for per-assignee messages.
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS assignee_id TEXT;
@@ -0,0 +1,2 @@
+-- The user who filed the issue, for `mention_creator`.
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS creator_id TEXT;
@@ -0,0 +1,2 @@
+-- The reminder comment's ID, for threading later reminders under it.
+ALTER TABLE reminders ADD COLUMN IF NOT EXISTS comment_id TEXT;
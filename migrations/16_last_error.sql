@@ -0,0 +1,2 @@
+-- Why the most recent attempt to remind the issue failed, if it did.
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS last_error TEXT;
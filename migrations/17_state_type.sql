@@ -0,0 +1,3 @@
+-- The type of the issue's state (e.g., `started` or `completed`), so that
+-- issues in `never_remind_types` are never reminded.
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS state_type TEXT;
@@ -0,0 +1,7 @@
+-- Hashes of recently received webhooks, for `webhook_dedup_window`.
+CREATE TABLE IF NOT EXISTS webhook_hashes (
+    hash TEXT PRIMARY KEY,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS webhook_hashes_received_at ON webhook_hashes (received_at);
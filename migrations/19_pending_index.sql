@@ -0,0 +1,11 @@
+-- Keeps `dequeue_issue` from scanning every reminded issue, which otherwise
+-- accumulate forever (unless `reminded_retention` is set). With 200k issues, 1%
+-- pending, `EXPLAIN` of the dequeue query shows:
+--
+--   Limit
+--     ->  LockRows
+--           ->  Index Scan using issues_pending_updated_at on issues
+--
+-- or, for a generic plan that can't rule out `dequeue_order = 'priority'`, a
+-- Sort over that same index scan.
+CREATE INDEX IF NOT EXISTS issues_pending_updated_at ON issues (updated_at) WHERE reminded = FALSE;
@@ -0,0 +1,2 @@
+-- Who moved the issue into the target status, for the message template.
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS actor TEXT;
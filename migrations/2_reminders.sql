@@ -0,0 +1,11 @@
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS team_key VARCHAR;
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS reminded_at TIMESTAMPTZ;
+
+CREATE TABLE IF NOT EXISTS reminders (
+    id BIGSERIAL PRIMARY KEY,
+    issue_id VARCHAR NOT NULL,
+    identifier VARCHAR NOT NULL,
+    team_key VARCHAR,
+    entered_at TIMESTAMPTZ NOT NULL,
+    reminded_at TIMESTAMPTZ NOT NULL
+);
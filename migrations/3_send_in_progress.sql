@@ -0,0 +1 @@
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS send_started_at TIMESTAMPTZ;
@@ -0,0 +1,9 @@
+-- `id` is authoritative; `identifier` (e.g., "HSI-339") is a human-facing alias
+-- that Linear may change (e.g., when an issue moves teams). Drop any stale rows
+-- sharing an identifier before enforcing uniqueness.
+DELETE FROM issues a
+USING issues b
+WHERE a.identifier = b.identifier
+  AND (a.updated_at, a.id) < (b.updated_at, b.id);
+
+CREATE UNIQUE INDEX IF NOT EXISTS issues_identifier_key ON issues (identifier);
@@ -0,0 +1 @@
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS next_attempt_at TIMESTAMPTZ;
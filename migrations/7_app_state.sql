@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS app_state (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    paused BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+INSERT INTO app_state (id) VALUES (TRUE) ON CONFLICT DO NOTHING;
@@ -0,0 +1,4 @@
+-- Snapshot the effective config at enqueue time, so later config changes don't
+-- retroactively affect already-queued issues.
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS message TEXT;
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS remind_after TIMESTAMPTZ;
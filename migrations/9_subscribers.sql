@@ -0,0 +1 @@
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS subscriber_ids TEXT[] NOT NULL DEFAULT '{}';
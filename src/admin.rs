@@ -0,0 +1,176 @@
+//! Administrative endpoints, which require `Authorization: Bearer <admin_token>`.
+//!
+//! These are disabled (404) unless an `admin_token` is configured.
+use std::{sync::Arc, time::Duration};
+
+use chrono::{TimeDelta, Utc};
+use rocket::{
+    delete, get,
+    http::Status,
+    outcome::Outcome,
+    post, put,
+    request::{self, FromRequest, Request},
+    serde::json::Json,
+    State,
+};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::{
+    deserialize_duration, set_paused, store_time_to_remind, AppConfig, AppState, Result,
+    ResyncError, ResyncSummary, TickSummary,
+};
+
+/// Request guard for administrative endpoints.
+pub struct Admin;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Admin {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let Some(config) = req.rocket().state::<Arc<AppConfig>>() else {
+            return Outcome::Error((Status::InternalServerError, ()));
+        };
+        let Some(admin_token) = &config.admin_token else {
+            return Outcome::Error((Status::NotFound, ()));
+        };
+
+        let token = req
+            .headers()
+            .get_one("Authorization")
+            .and_then(|h| h.strip_prefix("Bearer "));
+        match token {
+            Some(token) if token == admin_token.expose_secret() => Outcome::Success(Admin),
+            _ => Outcome::Error((Status::Unauthorized, ())),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Paused {
+    paused: bool,
+}
+
+/// Stop sending reminders. Webhooks are still accepted and queued.
+#[post("/pause")]
+pub async fn pause(_admin: Admin, state: &State<AppState>) -> Result<Json<Paused>> {
+    set_paused(&state.pool, true).await?;
+    info!("paused reminders");
+    Ok(Json(Paused { paused: true }))
+}
+
+/// Resume sending reminders, including any that came due while paused.
+#[post("/resume")]
+pub async fn resume(_admin: Admin, state: &State<AppState>) -> Result<Json<Paused>> {
+    set_paused(&state.pool, false).await?;
+    info!("resumed reminders");
+    Ok(Json(Paused { paused: false }))
+}
+
+/// Run one iteration of the reminder worker now, rather than waiting for the
+/// next tick, and report what it did.
+#[post("/tick")]
+pub async fn tick(_admin: Admin, state: &State<AppState>) -> Result<Json<TickSummary>> {
+    let summary = state.worker.tick().await?;
+    info!(summary=?summary, "ran worker tick");
+    Ok(Json(summary))
+}
+
+/// Reconcile the queue with the issues currently in the target status in
+/// Linear, e.g., after webhooks were missed during an outage.
+#[post("/resync")]
+pub async fn resync(_admin: Admin, state: &State<AppState>) -> Result<Json<ResyncSummary>, Status> {
+    match state.worker.resync().await {
+        Ok(summary) => {
+            info!(summary=?summary, "resynced queue");
+            Ok(Json(summary))
+        }
+        Err(ResyncError::Linear(e)) => {
+            error!(err=%e, "failed to resync queue, could not list issues from linear");
+            Err(Status::BadGateway)
+        }
+        Err(ResyncError::Database(e)) => {
+            error!(err=?e, "failed to resync queue");
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+/// The configuration this instance parsed at startup, with secrets redacted
+/// to their length, and outbound URLs to their origin. Runtime overrides,
+/// e.g., of `time_to_remind`, aren't reflected.
+#[get("/config")]
+pub fn effective_config(_admin: Admin, config: &State<Arc<AppConfig>>) -> Json<&AppConfig> {
+    Json(config.as_ref())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TimeToRemindUpdate {
+    #[serde(deserialize_with = "deserialize_duration")]
+    time_to_remind: Duration,
+    /// Whether to shift the timers of already-queued issues by the change,
+    /// rather than only applying it to issues queued afterwards.
+    #[serde(default)]
+    reschedule_queued: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TimeToRemind {
+    time_to_remind: String,
+    rescheduled: u64,
+}
+
+/// Override `time_to_remind` without a restart. Persists across restarts.
+#[put("/time_to_remind", format = "json", data = "<update>")]
+pub async fn set_time_to_remind(
+    _admin: Admin,
+    state: &State<AppState>,
+    update: Json<TimeToRemindUpdate>,
+) -> Result<Json<TimeToRemind>, Status> {
+    let time_to_remind = TimeDelta::from_std(update.time_to_remind)
+        .ok()
+        .filter(|delta| Utc::now().checked_sub_signed(*delta).is_some())
+        .ok_or(Status::BadRequest)?;
+    if state.schedule.get().expires_before_due(time_to_remind) {
+        warn!(time_to_remind=?update.time_to_remind, "refused a time_to_remind no shorter than max_issue_age");
+        return Err(Status::BadRequest);
+    }
+    update_time_to_remind(state, Some(time_to_remind), update.reschedule_queued).await
+}
+
+/// Clear the override, reverting to the configured `time_to_remind`.
+#[delete("/time_to_remind?<reschedule_queued>")]
+pub async fn reset_time_to_remind(
+    _admin: Admin,
+    state: &State<AppState>,
+    reschedule_queued: Option<bool>,
+) -> Result<Json<TimeToRemind>, Status> {
+    update_time_to_remind(state, None, reschedule_queued.unwrap_or(false)).await
+}
+
+async fn update_time_to_remind(
+    state: &AppState,
+    time_to_remind: Option<TimeDelta>,
+    reschedule_queued: bool,
+) -> Result<Json<TimeToRemind>, Status> {
+    let old = state.schedule.get().time_to_remind;
+    let new = state.schedule.resolve(time_to_remind);
+    let shift = reschedule_queued.then_some(new - old);
+    let rescheduled = store_time_to_remind(&state.pool, time_to_remind, shift)
+        .await
+        .map_err(|e| {
+            error!(err=?e.0, "failed to store time_to_remind");
+            Status::InternalServerError
+        })?;
+    state.schedule.set_time_to_remind(new);
+    info!(old=%old, new=%new, rescheduled, "updated time_to_remind");
+    Ok(Json(TimeToRemind {
+        time_to_remind: humantime::format_duration(new.to_std().unwrap_or_default()).to_string(),
+        rescheduled,
+    }))
+}
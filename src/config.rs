@@ -0,0 +1,122 @@
+//! Environment-specific configuration defaults, selected by `LR_ENVIRONMENT`.
+use std::{env, fmt, str::FromStr};
+
+use rocket::figment::{providers::Serialized, Figment};
+
+/// Where the server is running, which decides how strict its defaults are.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Environment {
+    /// Running locally, e.g., replaying captured webhooks while developing.
+    Local,
+    /// Deployed, where everything is enforced.
+    #[default]
+    Production,
+}
+
+impl Environment {
+    /// The environment named by `LR_ENVIRONMENT`, or production if unset.
+    pub fn from_env() -> Result<Self, InvalidEnvironment> {
+        match env::var("LR_ENVIRONMENT") {
+            Ok(name) => name.parse(),
+            Err(_) => Ok(Environment::Production),
+        }
+    }
+
+    /// Defaults for the settings that differ by environment. Anything
+    /// configured explicitly still takes precedence.
+    ///
+    /// | Setting                      | Local    | Production |
+    /// | ---------------------------- | -------- | ---------- |
+    /// | `linear.replay_max_age`      | `1day`   | `60s`      |
+    /// | `linear.clock_skew_tolerance`| `1day`   | `60s`      |
+    /// | `linear.verify_signatures`   | `false`  | `true`     |
+    /// | `log_level`                  | `debug`  | `normal`   |
+    pub fn defaults(self) -> Figment {
+        match self {
+            // Accept stale and unsigned webhooks, so captured payloads can be
+            // replayed with `curl`.
+            Environment::Local => Figment::new()
+                .join(Serialized::default("linear.replay_max_age", "1day"))
+                .join(Serialized::default("linear.clock_skew_tolerance", "1day"))
+                .join(Serialized::default("linear.verify_signatures", false))
+                .join(Serialized::default("log_level", "debug")),
+            // The defaults in `AppConfig` are already the strict ones.
+            Environment::Production => Figment::new(),
+        }
+    }
+}
+
+impl FromStr for Environment {
+    type Err = InvalidEnvironment;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "local" => Ok(Environment::Local),
+            "production" => Ok(Environment::Production),
+            _ => Err(InvalidEnvironment(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Environment::Local => write!(f, "local"),
+            Environment::Production => write!(f, "production"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidEnvironment(String);
+
+impl fmt::Display for InvalidEnvironment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid LR_ENVIRONMENT {:?}, expected `local` or `production`",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for InvalidEnvironment {}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn parses_environments() {
+        assert_eq!("local".parse::<Environment>().unwrap(), Environment::Local);
+        assert_eq!(
+            "Production".parse::<Environment>().unwrap(),
+            Environment::Production
+        );
+        assert!("staging".parse::<Environment>().is_err());
+        assert_eq!(Environment::Local.to_string(), "local");
+    }
+
+    #[test]
+    fn only_local_relaxes_defaults() {
+        let local = Environment::Local.defaults();
+        assert_eq!(
+            local
+                .extract_inner::<bool>("linear.verify_signatures")
+                .unwrap(),
+            false
+        );
+        assert_eq!(
+            local
+                .extract_inner::<String>("linear.replay_max_age")
+                .unwrap(),
+            "1day"
+        );
+        assert!(Environment::Production
+            .defaults()
+            .extract_inner::<bool>("linear.verify_signatures")
+            .is_err());
+    }
+}
@@ -0,0 +1,741 @@
+//! A minimal client for Linear's GraphQL API.
+//!
+//! Ref: <https://developers.linear.app/docs/graphql/working-with-the-graphql-api>
+use std::{collections::HashMap, fmt, sync::Arc, time::Duration};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use reqwest::{
+    header::{self, HeaderMap},
+    Response, StatusCode,
+};
+use rocket::serde::json::{serde_json, Value};
+use secrecy::{ExposeSecret, SecretString};
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::metrics::Metrics;
+
+const LINEAR_API_URL: &str = "https://api.linear.app/graphql";
+
+/// How long to wait on the Linear API before giving up on a request.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The rate limits Linear reports on every response.
+pub const RATE_LIMIT_KINDS: [&str; 2] = ["requests", "complexity"];
+
+/// One of Linear's rate limits for an API key, per its `X-RateLimit-*`
+/// response headers.
+///
+/// Ref: <https://developers.linear.app/docs/graphql/working-with-the-graphql-api/rate-limiting>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub limit: u64,
+    pub remaining: u64,
+    /// When the quota resets, in Unix milliseconds.
+    pub reset: u64,
+}
+
+impl RateLimit {
+    /// The rate limit of the given kind, e.g., `requests`, if the headers
+    /// report all of it.
+    pub fn from_headers(headers: &HeaderMap, kind: &str) -> Option<Self> {
+        let field = |field: &str| {
+            headers
+                .get(format!("x-ratelimit-{kind}-{field}"))?
+                .to_str()
+                .ok()?
+                .trim()
+                .parse()
+                .ok()
+        };
+        Some(RateLimit {
+            limit: field("limit")?,
+            remaining: field("remaining")?,
+            reset: field("reset")?,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum LinearError {
+    Request(reqwest::Error),
+    Status(StatusCode, String),
+    GraphQl(String),
+}
+
+impl fmt::Display for LinearError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinearError::Request(e) => write!(f, "request failed: {e}"),
+            LinearError::Status(status, body) => write!(f, "unexpected status {status}: {body}"),
+            LinearError::GraphQl(errors) => write!(f, "graphql errors: {errors}"),
+        }
+    }
+}
+
+impl std::error::Error for LinearError {}
+
+impl LinearError {
+    /// Whether Linear rejected our API key, e.g., because it was revoked, in
+    /// which case retrying won't help.
+    pub fn is_auth(&self) -> bool {
+        match self {
+            LinearError::Request(_) => false,
+            LinearError::Status(status, body) => is_auth_failure(*status, body),
+            LinearError::GraphQl(errors) => {
+                serde_json::from_str::<Value>(errors).is_ok_and(|errors| has_auth_error(&errors))
+            }
+        }
+    }
+}
+
+/// Whether a response means Linear rejected our API key.
+pub fn is_auth_failure(status: StatusCode, body: &str) -> bool {
+    matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)
+        || serde_json::from_str::<Value>(body).is_ok_and(|body| has_auth_error(&body["errors"]))
+}
+
+/// Whether GraphQL `errors` include an authentication error, which Linear may
+/// send with a `400 Bad Request` rather than a `401 Unauthorized`.
+pub fn has_auth_error(errors: &Value) -> bool {
+    errors.as_array().is_some_and(|errors| {
+        errors.iter().any(|error| {
+            matches!(
+                error.pointer("/extensions/code").and_then(Value::as_str),
+                Some("AUTHENTICATION_ERROR" | "FORBIDDEN")
+            )
+        })
+    })
+}
+
+impl From<reqwest::Error> for LinearError {
+    fn from(e: reqwest::Error) -> Self {
+        LinearError::Request(e)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQlResponse<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<Value>,
+}
+
+/// An issue, as listed by [`LinearClient::issues`].
+#[derive(Debug, Deserialize)]
+pub struct IssueNode {
+    pub id: String,
+    pub identifier: String,
+    pub title: String,
+    /// 0 = No priority, 1 = Urgent, 2 = High, 3 = Medium, 4 = Low
+    pub priority: f64,
+    pub url: String,
+    pub estimate: Option<f64>,
+    #[serde(rename = "dueDate")]
+    pub due_date: Option<NaiveDate>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    pub state: State,
+    pub team: Team,
+    pub project: Option<Project>,
+    pub assignee: Option<User>,
+    pub creator: Option<User>,
+    pub labels: Connection<Named>,
+    pub subscribers: Connection<User>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Named {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct State {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub state_type: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Team {
+    pub key: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct User {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Connection<T> {
+    pub nodes: Vec<T>,
+}
+
+/// Issue fields that webhooks don't carry, fetched for enriched message
+/// templates when the reminder is sent.
+#[derive(Debug)]
+pub struct EnrichedIssue {
+    pub url: String,
+    pub assignee: Option<String>,
+    pub assignee_email: Option<String>,
+    pub labels: Vec<String>,
+    /// The cycle's name, or its number if it has none.
+    pub cycle: Option<String>,
+    pub project: Option<String>,
+}
+
+/// A client per team that authorized the integration separately, falling
+/// back to a default client for other teams.
+#[derive(Debug, Clone)]
+pub struct LinearClients {
+    default: LinearClient,
+    teams: HashMap<String, LinearClient>,
+}
+
+impl LinearClients {
+    pub fn new(default: LinearClient, team_api_keys: &HashMap<String, SecretString>) -> Self {
+        let teams = team_api_keys
+            .iter()
+            .map(|(team, api_key)| (team.clone(), default.with_api_key(team, api_key.clone())))
+            .collect();
+        LinearClients { default, teams }
+    }
+
+    /// The client for the given team key.
+    pub fn for_team(&self, team: Option<&str>) -> &LinearClient {
+        team.and_then(|team| self.teams.get(team))
+            .unwrap_or(&self.default)
+    }
+
+    /// Every distinct client, starting with the default one.
+    pub fn all(&self) -> impl Iterator<Item = &LinearClient> {
+        std::iter::once(&self.default).chain(self.teams.values())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LinearClient {
+    http: reqwest::Client,
+    api_key: SecretString,
+    /// Which API key this is in metrics: `default`, or the team it's for.
+    name: String,
+    metrics: Option<Arc<Metrics>>,
+}
+
+impl LinearClient {
+    pub fn new(api_key: SecretString, http: reqwest::Client) -> Self {
+        LinearClient {
+            http,
+            api_key,
+            name: "default".to_string(),
+            metrics: None,
+        }
+    }
+
+    /// Record the rate limits reported on responses in `metrics`.
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// A client for the same HTTP connection pool, with the team's API key.
+    pub fn with_api_key(&self, team: &str, api_key: SecretString) -> Self {
+        LinearClient {
+            http: self.http.clone(),
+            api_key,
+            name: team.to_string(),
+            metrics: self.metrics.clone(),
+        }
+    }
+
+    /// Send a GraphQL request, returning the raw response.
+    pub async fn request(&self, query: &str, variables: Value) -> reqwest::Result<Response> {
+        let res = self
+            .http
+            .post(LINEAR_API_URL)
+            .header(header::AUTHORIZATION, self.api_key.expose_secret())
+            .header(header::CONTENT_TYPE, "application/json")
+            .timeout(REQUEST_TIMEOUT)
+            .json(&serde_json::json!({ "query": query, "variables": variables }))
+            .send()
+            .await?;
+        if let Some(metrics) = &self.metrics {
+            for kind in RATE_LIMIT_KINDS {
+                if let Some(limit) = RateLimit::from_headers(res.headers(), kind) {
+                    metrics.record_rate_limit(&self.name, kind, limit);
+                }
+            }
+        }
+        Ok(res)
+    }
+
+    /// Send a GraphQL request and deserialize its `data`.
+    async fn query<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        variables: Value,
+    ) -> Result<T, LinearError> {
+        let res = self.request(query, variables).await?;
+        let status = res.status();
+        if !status.is_success() {
+            return Err(LinearError::Status(
+                status,
+                res.text().await.unwrap_or_default(),
+            ));
+        }
+        let res: GraphQlResponse<T> = res.json().await?;
+        match res.data {
+            Some(data) if res.errors.is_empty() => Ok(data),
+            _ => Err(LinearError::GraphQl(Value::from(res.errors).to_string())),
+        }
+    }
+
+    /// When the issue most recently moved into the given status, according to
+    /// the 50 most recent entries of its history.
+    pub async fn status_entered_at(
+        &self,
+        issue_id: &str,
+        status: &str,
+    ) -> Result<Option<DateTime<Utc>>, LinearError> {
+        #[derive(Deserialize)]
+        struct Data {
+            issue: IssueHistory,
+        }
+        #[derive(Deserialize)]
+        struct IssueHistory {
+            history: Nodes,
+        }
+        #[derive(Deserialize)]
+        struct Nodes {
+            nodes: Vec<HistoryEntry>,
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct HistoryEntry {
+            created_at: DateTime<Utc>,
+            to_state: Option<State>,
+        }
+        #[derive(Deserialize)]
+        struct State {
+            name: String,
+        }
+
+        let data: Data = self
+            .query(
+                r#"query IssueHistory($id: String!) {
+                    issue(id: $id) {
+                        history(first: 50, orderBy: createdAt) {
+                            nodes {
+                                createdAt
+                                toState { name }
+                            }
+                        }
+                    }
+                }"#,
+                serde_json::json!({ "id": issue_id }),
+            )
+            .await?;
+        Ok(data
+            .issue
+            .history
+            .nodes
+            .into_iter()
+            .filter(|entry| entry.to_state.as_ref().is_some_and(|s| s.name == status))
+            .map(|entry| entry.created_at)
+            .max())
+    }
+
+    /// All issues matching the given `IssueFilter`, following pagination.
+    pub async fn issues(&self, filter: Value) -> Result<Vec<IssueNode>, LinearError> {
+        #[derive(Deserialize)]
+        struct Data {
+            issues: Page,
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Page {
+            nodes: Vec<IssueNode>,
+            page_info: PageInfo,
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct PageInfo {
+            has_next_page: bool,
+            end_cursor: Option<String>,
+        }
+
+        let mut issues = Vec::new();
+        let mut after = None;
+        loop {
+            let data: Data = self
+                .query(
+                    r#"query Issues($filter: IssueFilter, $after: String) {
+                        issues(first: 100, after: $after, filter: $filter) {
+                            nodes {
+                                id identifier title priority url estimate dueDate createdAt
+                                state { name type }
+                                team { key }
+                                project { id name }
+                                assignee { id name }
+                                creator { id name }
+                                labels { nodes { name } }
+                                subscribers { nodes { id name } }
+                            }
+                            pageInfo { hasNextPage endCursor }
+                        }
+                    }"#,
+                    serde_json::json!({ "filter": filter, "after": after }),
+                )
+                .await?;
+            issues.extend(data.issues.nodes);
+            match data.issues.page_info {
+                PageInfo {
+                    has_next_page: true,
+                    end_cursor: Some(cursor),
+                } => after = Some(cursor),
+                _ => break,
+            }
+        }
+        Ok(issues)
+    }
+
+    /// The issue's current fields, for rendering enriched message templates.
+    pub async fn enriched_issue(&self, issue_id: &str) -> Result<EnrichedIssue, LinearError> {
+        #[derive(Deserialize)]
+        struct Data {
+            issue: Issue,
+        }
+        #[derive(Deserialize)]
+        struct Issue {
+            url: String,
+            assignee: Option<User>,
+            labels: Connection<Named>,
+            cycle: Option<Cycle>,
+            project: Option<Named>,
+        }
+        #[derive(Deserialize)]
+        struct User {
+            name: String,
+            email: String,
+        }
+        #[derive(Deserialize)]
+        struct Cycle {
+            name: Option<String>,
+            number: f64,
+        }
+
+        let data: Data = self
+            .query(
+                r#"query EnrichedIssue($id: String!) {
+                    issue(id: $id) {
+                        url
+                        assignee { name email }
+                        labels { nodes { name } }
+                        cycle { name number }
+                        project { name }
+                    }
+                }"#,
+                serde_json::json!({ "id": issue_id }),
+            )
+            .await?;
+        let issue = data.issue;
+        Ok(EnrichedIssue {
+            url: issue.url,
+            assignee_email: issue.assignee.as_ref().map(|user| user.email.clone()),
+            assignee: issue.assignee.map(|user| user.name),
+            labels: issue.labels.nodes.into_iter().map(|l| l.name).collect(),
+            cycle: issue.cycle.map(|cycle| {
+                cycle
+                    .name
+                    .unwrap_or_else(|| format!("Cycle {}", cycle.number))
+            }),
+            project: issue.project.map(|project| project.name),
+        })
+    }
+
+    /// The bodies of up to 50 comments posted on the issue since `since`.
+    pub async fn recent_comments(
+        &self,
+        issue_id: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<String>, LinearError> {
+        #[derive(Deserialize)]
+        struct Data {
+            issue: IssueComments,
+        }
+        #[derive(Deserialize)]
+        struct IssueComments {
+            comments: Nodes,
+        }
+        #[derive(Deserialize)]
+        struct Nodes {
+            nodes: Vec<Comment>,
+        }
+        #[derive(Deserialize)]
+        struct Comment {
+            body: String,
+        }
+
+        let data: Data = self
+            .query(
+                r#"query IssueComments($id: String!, $since: DateTimeOrDuration!) {
+                    issue(id: $id) {
+                        comments(first: 50, filter: { createdAt: { gte: $since } }) {
+                            nodes { body }
+                        }
+                    }
+                }"#,
+                serde_json::json!({ "id": issue_id, "since": since }),
+            )
+            .await?;
+        Ok(data
+            .issue
+            .comments
+            .nodes
+            .into_iter()
+            .map(|comment| comment.body)
+            .collect())
+    }
+
+    /// Whether the comment still exists, i.e., hasn't been deleted.
+    pub async fn comment_exists(&self, comment_id: &str) -> Result<bool, LinearError> {
+        #[derive(Deserialize)]
+        struct Data {
+            comments: Nodes,
+        }
+        #[derive(Deserialize)]
+        struct Nodes {
+            nodes: Vec<Value>,
+        }
+
+        // Filtering, rather than fetching the comment by ID, tells a deleted
+        // comment apart from other errors.
+        let data: Data = self
+            .query(
+                r#"query Comment($id: ID!) {
+                    comments(filter: { id: { eq: $id } }) {
+                        nodes { id }
+                    }
+                }"#,
+                serde_json::json!({ "id": comment_id }),
+            )
+            .await?;
+        Ok(!data.comments.nodes.is_empty())
+    }
+
+    /// The profile URLs of the given users, skipping any that don't exist.
+    pub async fn profile_urls(&self, user_ids: &[String]) -> Result<Vec<String>, LinearError> {
+        #[derive(Deserialize)]
+        struct Data {
+            users: Nodes,
+        }
+        #[derive(Deserialize)]
+        struct Nodes {
+            nodes: Vec<User>,
+        }
+        #[derive(Deserialize)]
+        struct User {
+            url: String,
+        }
+
+        let data: Data = self
+            .query(
+                r#"query Users($ids: [ID!]!) {
+                    users(first: 250, filter: { id: { in: $ids } }) {
+                        nodes { url }
+                    }
+                }"#,
+                serde_json::json!({ "ids": user_ids }),
+            )
+            .await?;
+        Ok(data.users.nodes.into_iter().map(|user| user.url).collect())
+    }
+
+    /// The URL of a team, by ID or key, which Linear renders in a comment as
+    /// a mention of the team.
+    pub async fn team_url(&self, team: &str) -> Result<String, LinearError> {
+        #[derive(Deserialize)]
+        struct Data {
+            team: TeamWithOrganization,
+        }
+        #[derive(Deserialize)]
+        struct TeamWithOrganization {
+            key: String,
+            organization: Organization,
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Organization {
+            url_key: String,
+        }
+
+        let data: Data = self
+            .query(
+                r#"query Team($id: String!) {
+                    team(id: $id) {
+                        key
+                        organization { urlKey }
+                    }
+                }"#,
+                serde_json::json!({ "id": team }),
+            )
+            .await?;
+        Ok(format!(
+            "https://linear.app/{}/team/{}",
+            data.team.organization.url_key, data.team.key
+        ))
+    }
+
+    /// The ID of the user the API key belongs to.
+    pub async fn viewer_id(&self) -> Result<String, LinearError> {
+        #[derive(Deserialize)]
+        struct Data {
+            viewer: Viewer,
+        }
+        #[derive(Deserialize)]
+        struct Viewer {
+            id: String,
+        }
+
+        let data: Data = self
+            .query("query { viewer { id } }", serde_json::json!({}))
+            .await?;
+        Ok(data.viewer.id)
+    }
+
+    /// The ID of the issue's current assignee, if any.
+    pub async fn assignee_id(&self, issue_id: &str) -> Result<Option<String>, LinearError> {
+        #[derive(Deserialize)]
+        struct Data {
+            issue: Issue,
+        }
+        #[derive(Deserialize)]
+        struct Issue {
+            assignee: Option<User>,
+        }
+        #[derive(Deserialize)]
+        struct User {
+            id: String,
+        }
+
+        let data: Data = self
+            .query(
+                "query IssueAssignee($id: String!) { issue(id: $id) { assignee { id } } }",
+                serde_json::json!({ "id": issue_id }),
+            )
+            .await?;
+        Ok(data.issue.assignee.map(|user| user.id))
+    }
+
+    /// Set (or clear) the issue's assignee.
+    pub async fn set_assignee(
+        &self,
+        issue_id: &str,
+        assignee_id: Option<&str>,
+    ) -> Result<(), LinearError> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Data {
+            issue_update: Payload,
+        }
+        #[derive(Deserialize)]
+        struct Payload {
+            success: bool,
+        }
+
+        let data: Data = self
+            .query(
+                r#"mutation IssueAssign($id: String!, $assigneeId: String) {
+                    issueUpdate(id: $id, input: { assigneeId: $assigneeId }) { success }
+                }"#,
+                serde_json::json!({ "id": issue_id, "assigneeId": assignee_id }),
+            )
+            .await?;
+        if !data.issue_update.success {
+            return Err(LinearError::GraphQl(
+                "issueUpdate was unsuccessful".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn picks_clients_per_team() {
+        let default = LinearClient::new("default".to_string().into(), reqwest::Client::new());
+        let team_api_keys = HashMap::from([("ENG".to_string(), "eng".to_string().into())]);
+        let clients = LinearClients::new(default, &team_api_keys);
+        let api_key = |team| clients.for_team(team).api_key.expose_secret().to_string();
+        assert_eq!(api_key(Some("ENG")), "eng");
+        assert_eq!(api_key(Some("OPS")), "default");
+        assert_eq!(api_key(None), "default");
+        assert_eq!(clients.all().count(), 2);
+    }
+
+    #[test]
+    fn parses_rate_limit_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-ratelimit-requests-limit",
+            HeaderValue::from_static("1500"),
+        );
+        headers.insert(
+            "x-ratelimit-requests-remaining",
+            HeaderValue::from_static(" 1499 "),
+        );
+        headers.insert(
+            "x-ratelimit-requests-reset",
+            HeaderValue::from_static("1711207931774"),
+        );
+        headers.insert(
+            "x-ratelimit-complexity-limit",
+            HeaderValue::from_static("250000"),
+        );
+        headers.insert(
+            "x-ratelimit-complexity-remaining",
+            HeaderValue::from_static("lots"),
+        );
+
+        assert_eq!(
+            RateLimit::from_headers(&headers, "requests"),
+            Some(RateLimit {
+                limit: 1500,
+                remaining: 1499,
+                reset: 1711207931774,
+            })
+        );
+        assert_eq!(RateLimit::from_headers(&headers, "complexity"), None);
+    }
+
+    #[test]
+    fn detects_auth_failures() {
+        assert!(is_auth_failure(StatusCode::UNAUTHORIZED, ""));
+        assert!(is_auth_failure(
+            StatusCode::BAD_REQUEST,
+            r#"{"errors": [{"extensions": {"code": "AUTHENTICATION_ERROR"}}]}"#
+        ));
+        assert!(!is_auth_failure(
+            StatusCode::BAD_REQUEST,
+            r#"{"errors": [{"extensions": {"code": "INVALID_INPUT"}}]}"#
+        ));
+        assert!(!is_auth_failure(StatusCode::INTERNAL_SERVER_ERROR, "oops"));
+
+        let errors = serde_json::json!([{"extensions": {"code": "FORBIDDEN"}}]);
+        assert!(has_auth_error(&errors));
+        assert!(LinearError::GraphQl(errors.to_string()).is_auth());
+        assert!(!LinearError::GraphQl("not json".to_string()).is_auth());
+    }
+}
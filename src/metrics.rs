@@ -0,0 +1,358 @@
+//! Process metrics, exposed in the Prometheus text format.
+//!
+//! Ref: <https://prometheus.io/docs/instrumenting/exposition_formats/>
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    time::Instant,
+};
+
+use chrono::{DateTime, Utc};
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    get, Data, Request, Response, State,
+};
+
+use crate::{linear::RateLimit, AppState};
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// Worker ticks where the database couldn't be queried for due issues.
+    pub worker_db_errors: AtomicU64,
+    /// Webhooks turned away under backpressure.
+    pub webhooks_shed: AtomicU64,
+    /// Webhooks rejected because their signature didn't match.
+    pub signature_failures: AtomicU64,
+    /// Reminders that failed because Linear rejected the API key.
+    pub auth_failures: AtomicU64,
+    /// When the worker last completed an iteration, in Unix seconds, or 0 if
+    /// it hasn't yet.
+    worker_last_tick: AtomicU64,
+    /// The latest rate limits reported by Linear, by API key and kind.
+    rate_limits: Mutex<BTreeMap<(String, &'static str), RateLimit>>,
+    /// HTTP requests served, by route.
+    http: Mutex<BTreeMap<RequestLabels, RequestStats>>,
+}
+
+/// What HTTP requests are counted by. Routes are used rather than paths, so
+/// that arbitrary paths can't blow up the number of series.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct RequestLabels {
+    method: String,
+    route: String,
+    status: u16,
+}
+
+#[derive(Debug, Default)]
+struct RequestStats {
+    count: u64,
+    seconds: f64,
+}
+
+impl Metrics {
+    fn render(&self) -> String {
+        let mut out = String::new();
+        counter(
+            &mut out,
+            "linear_reminder_worker_db_errors_total",
+            "Worker ticks where the database couldn't be queried for due issues.",
+            &self.worker_db_errors,
+        );
+        counter(
+            &mut out,
+            "linear_reminder_webhooks_shed_total",
+            "Webhooks turned away under backpressure.",
+            &self.webhooks_shed,
+        );
+        counter(
+            &mut out,
+            "linear_reminder_signature_failures_total",
+            "Webhooks rejected because their signature didn't match.",
+            &self.signature_failures,
+        );
+        counter(
+            &mut out,
+            "linear_reminder_auth_failures_total",
+            "Reminders that failed because Linear rejected the API key.",
+            &self.auth_failures,
+        );
+        gauge(
+            &mut out,
+            "linear_reminder_worker_last_tick_timestamp_seconds",
+            "When the worker last completed an iteration, in Unix seconds.",
+            &self.worker_last_tick,
+        );
+
+        let rate_limits = self
+            .rate_limits
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        rate_limit_gauge(
+            &mut out,
+            "linear_reminder_linear_rate_limit",
+            "Linear's rate limit for the API key.",
+            &rate_limits,
+            |limit| limit.limit.to_string(),
+        );
+        rate_limit_gauge(
+            &mut out,
+            "linear_reminder_linear_rate_limit_remaining",
+            "How much of Linear's rate limit is left until it resets.",
+            &rate_limits,
+            |limit| limit.remaining.to_string(),
+        );
+        rate_limit_gauge(
+            &mut out,
+            "linear_reminder_linear_rate_limit_reset_timestamp_seconds",
+            "When Linear's rate limit resets, in Unix seconds.",
+            &rate_limits,
+            |limit| (limit.reset as f64 / 1000.0).to_string(),
+        );
+        drop(rate_limits);
+
+        let http = self.http.lock().unwrap_or_else(PoisonError::into_inner);
+        let _ = writeln!(
+            out,
+            "# HELP linear_reminder_http_requests_total HTTP requests served."
+        );
+        let _ = writeln!(out, "# TYPE linear_reminder_http_requests_total counter");
+        for (labels, stats) in http.iter() {
+            let _ = writeln!(
+                out,
+                "linear_reminder_http_requests_total{} {}",
+                labels.render(),
+                stats.count
+            );
+        }
+        let _ = writeln!(
+            out,
+            "# HELP linear_reminder_http_request_duration_seconds Time spent serving HTTP requests."
+        );
+        let _ = writeln!(
+            out,
+            "# TYPE linear_reminder_http_request_duration_seconds summary"
+        );
+        for (labels, stats) in http.iter() {
+            let labels = labels.render();
+            let _ = writeln!(
+                out,
+                "linear_reminder_http_request_duration_seconds_sum{labels} {}",
+                stats.seconds
+            );
+            let _ = writeln!(
+                out,
+                "linear_reminder_http_request_duration_seconds_count{labels} {}",
+                stats.count
+            );
+        }
+        out
+    }
+
+    /// Record that the worker completed an iteration.
+    pub fn record_worker_tick(&self, at: DateTime<Utc>) {
+        let secs = u64::try_from(at.timestamp()).unwrap_or_default();
+        self.worker_last_tick.store(secs, Ordering::Relaxed);
+    }
+
+    /// When the worker last completed an iteration, if it has.
+    pub fn worker_last_tick(&self) -> Option<DateTime<Utc>> {
+        match self.worker_last_tick.load(Ordering::Relaxed) {
+            0 => None,
+            secs => DateTime::from_timestamp(i64::try_from(secs).ok()?, 0),
+        }
+    }
+
+    /// Record a rate limit Linear reported for an API key.
+    pub fn record_rate_limit(&self, client: &str, kind: &'static str, limit: RateLimit) {
+        let mut rate_limits = self
+            .rate_limits
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        rate_limits.insert((client.to_string(), kind), limit);
+    }
+
+    fn record_request(&self, labels: RequestLabels, seconds: f64) {
+        let mut http = self.http.lock().unwrap_or_else(PoisonError::into_inner);
+        let stats = http.entry(labels).or_default();
+        stats.count += 1;
+        stats.seconds += seconds;
+    }
+}
+
+impl RequestLabels {
+    fn render(&self) -> String {
+        format!(
+            r#"{{method="{}",route="{}",status="{}"}}"#,
+            self.method,
+            escape_label(&self.route),
+            self.status
+        )
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn counter(out: &mut String, name: &str, help: &str, value: &AtomicU64) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} counter");
+    let _ = writeln!(out, "{name} {}", value.load(Ordering::Relaxed));
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: &AtomicU64) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} gauge");
+    let _ = writeln!(out, "{name} {}", value.load(Ordering::Relaxed));
+}
+
+/// A gauge of one field of each rate limit, labeled by API key and kind.
+fn rate_limit_gauge(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    rate_limits: &BTreeMap<(String, &'static str), RateLimit>,
+    value: impl Fn(&RateLimit) -> String,
+) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} gauge");
+    for ((client, kind), limit) in rate_limits {
+        let _ = writeln!(
+            out,
+            r#"{name}{{client="{}",kind="{kind}"}} {}"#,
+            escape_label(client),
+            value(limit)
+        );
+    }
+}
+
+/// Records the count, status, and latency of every HTTP request.
+pub struct HttpMetrics(pub Arc<Metrics>);
+
+/// When the request started, in request-local state.
+struct RequestStart(Option<Instant>);
+
+#[rocket::async_trait]
+impl Fairing for HttpMetrics {
+    fn info(&self) -> Info {
+        Info {
+            name: "HTTP metrics",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _data: &mut Data<'_>) {
+        req.local_cache(|| RequestStart(Some(Instant::now())));
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let Some(start) = req.local_cache(|| RequestStart(None)).0 else {
+            return;
+        };
+        let route = req
+            .route()
+            .map(|route| route.uri.to_string())
+            .unwrap_or_else(|| "unmatched".to_string());
+        self.0.record_request(
+            RequestLabels {
+                method: req.method().to_string(),
+                route,
+                status: res.status().code,
+            },
+            start.elapsed().as_secs_f64(),
+        );
+    }
+}
+
+#[get("/")]
+pub fn metrics(state: &State<AppState>) -> String {
+    state.metrics.render()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn escapes_labels() {
+        assert_eq!(escape_label(r#"a "b" \c"#), r#"a \"b\" \\c"#);
+    }
+
+    #[test]
+    fn records_worker_ticks() {
+        let metrics = Metrics::default();
+        assert_eq!(metrics.worker_last_tick(), None);
+        let at = DateTime::from_timestamp(1711207931, 0).unwrap();
+        metrics.record_worker_tick(at);
+        assert_eq!(metrics.worker_last_tick(), Some(at));
+    }
+
+    #[test]
+    fn recovers_from_poisoned_locks() {
+        let metrics = Arc::new(Metrics::default());
+        let poisoner = metrics.clone();
+        let _ = std::thread::spawn(move || {
+            let _http = poisoner.http.lock().unwrap();
+            panic!("poison the lock");
+        })
+        .join();
+        assert!(metrics.http.is_poisoned());
+
+        metrics.record_request(
+            RequestLabels {
+                method: "GET".to_string(),
+                route: "/".to_string(),
+                status: 200,
+            },
+            0.5,
+        );
+        assert!(metrics.render().contains(
+            r#"linear_reminder_http_requests_total{method="GET",route="/",status="200"} 1"#
+        ));
+    }
+
+    #[test]
+    fn renders_metrics() {
+        let metrics = Metrics::default();
+        metrics.worker_db_errors.fetch_add(2, Ordering::Relaxed);
+        metrics.signature_failures.fetch_add(2, Ordering::Relaxed);
+        metrics.record_rate_limit(
+            "team \"a\"",
+            "requests",
+            RateLimit {
+                limit: 1500,
+                remaining: 1499,
+                reset: 1711207931500,
+            },
+        );
+        let labels = RequestLabels {
+            method: "POST".to_string(),
+            route: "/".to_string(),
+            status: 200,
+        };
+        metrics.record_request(labels.clone(), 0.25);
+        metrics.record_request(labels, 0.5);
+
+        let rendered = metrics.render();
+        for line in [
+            "# TYPE linear_reminder_worker_db_errors_total counter",
+            "linear_reminder_worker_db_errors_total 2",
+            "# TYPE linear_reminder_signature_failures_total counter",
+            "linear_reminder_signature_failures_total 2",
+            "# TYPE linear_reminder_worker_last_tick_timestamp_seconds gauge",
+            "linear_reminder_worker_last_tick_timestamp_seconds 0",
+            r#"linear_reminder_linear_rate_limit_remaining{client="team \"a\"",kind="requests"} 1499"#,
+            r#"linear_reminder_linear_rate_limit_reset_timestamp_seconds{client="team \"a\"",kind="requests"} 1711207931.5"#,
+            r#"linear_reminder_http_requests_total{method="POST",route="/",status="200"} 2"#,
+            r#"linear_reminder_http_request_duration_seconds_sum{method="POST",route="/",status="200"} 0.75"#,
+        ] {
+            assert!(rendered.lines().any(|l| l == line), "missing {line:?}");
+        }
+    }
+}
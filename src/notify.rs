@@ -0,0 +1,220 @@
+//! Outbound notifications to services other than Linear.
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use reqwest::header::{self, HeaderMap, HeaderName, HeaderValue};
+use rocket::serde::json::serde_json;
+use secrecy::ExposeSecret;
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use crate::{
+    linear::REQUEST_TIMEOUT, Issue, NotificationConfig, PagerDutyConfig, PagerDutySeverity,
+    SignatureAlgorithm,
+};
+
+/// The header carrying the HMAC-SHA256 signature of outbound payloads.
+const SIGNATURE_HEADER: &str = "Linear-Reminder-Signature";
+
+#[derive(Debug, Serialize)]
+struct ReminderSent<'a> {
+    event: &'static str,
+    issue: &'a Issue,
+    message: &'a str,
+    reminded_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+struct ReminderFailed<'a> {
+    event: &'static str,
+    issue: &'a Issue,
+    message: &'a str,
+    error: &'a str,
+}
+
+/// Posts signed JSON payloads to a configured webhook URL.
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    http: reqwest::Client,
+    config: NotificationConfig,
+}
+
+/// A configured header that can't be sent.
+#[derive(Debug)]
+pub struct InvalidHeader(String);
+
+impl fmt::Display for InvalidHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid notification header {:?}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidHeader {}
+
+impl Notifier {
+    /// Fails if any of the configured `headers` isn't a valid header.
+    pub fn new(config: NotificationConfig) -> Result<Self, InvalidHeader> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &config.headers {
+            let invalid = || InvalidHeader(name.clone());
+            let name = HeaderName::try_from(name).map_err(|_| invalid())?;
+            let mut value = HeaderValue::try_from(value.expose_secret()).map_err(|_| invalid())?;
+            // e.g., an API key, so keep it out of debug output.
+            value.set_sensitive(true);
+            headers.insert(name, value);
+        }
+        let http = reqwest::Client::builder()
+            .default_headers(headers)
+            .build()
+            // Like `reqwest::Client::new`, this only fails if TLS can't be set
+            // up at all.
+            .expect("failed to build http client");
+        Ok(Notifier { http, config })
+    }
+
+    /// Let the configured webhook know a reminder was sent.
+    ///
+    /// Failures are only logged, since the reminder itself has already been
+    /// delivered.
+    pub async fn reminder_sent(&self, issue: &Issue, message: &str, reminded_at: DateTime<Utc>) {
+        let payload = ReminderSent {
+            event: "reminder_sent",
+            issue,
+            message,
+            reminded_at,
+        };
+        match self.post(&payload).await {
+            Ok(()) => debug!(issue=?issue, "sent reminder notification"),
+            Err(e) => warn!(issue=?issue, err=%e, "failed to send reminder notification"),
+        }
+    }
+
+    /// Let the configured webhook know a reminder couldn't be posted to
+    /// Linear, so that it isn't silently lost.
+    pub async fn reminder_failed(&self, issue: &Issue, message: &str, error: &str) {
+        let payload = ReminderFailed {
+            event: "reminder_failed",
+            issue,
+            message,
+            error,
+        };
+        match self.post(&payload).await {
+            Ok(()) => debug!(issue=?issue, "sent failed reminder notification"),
+            Err(e) => warn!(issue=?issue, err=%e, "failed to send failed reminder notification"),
+        }
+    }
+
+    async fn post<T: Serialize>(&self, payload: &T) -> reqwest::Result<()> {
+        let body = serde_json::to_string(payload).expect("payload is always serializable");
+        let signature =
+            SignatureAlgorithm::Sha256.sign(&body, self.config.signing_key.expose_secret());
+        self.http
+            .post(&self.config.url)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, signature)
+            .timeout(REQUEST_TIMEOUT)
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// A PagerDuty Events API v2 event.
+///
+/// Ref: <https://developer.pagerduty.com/docs/events-api-v2/overview/>
+#[derive(Debug, Serialize)]
+struct PagerDutyEvent<'a> {
+    routing_key: &'a str,
+    event_action: &'static str,
+    dedup_key: String,
+    /// Only for `trigger` events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payload: Option<PagerDutyPayload<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct PagerDutyPayload<'a> {
+    summary: String,
+    source: &'static str,
+    severity: PagerDutySeverity,
+    custom_details: &'a Issue,
+}
+
+/// Opens PagerDuty incidents for reminded issues, and optionally resolves them
+/// once the issues leave the target status.
+#[derive(Debug, Clone)]
+pub struct PagerDuty {
+    http: reqwest::Client,
+    config: PagerDutyConfig,
+}
+
+impl PagerDuty {
+    pub fn new(config: PagerDutyConfig) -> Self {
+        PagerDuty {
+            http: reqwest::Client::new(),
+            config,
+        }
+    }
+
+    /// Whether issues with this priority open incidents.
+    pub fn handles(&self, priority: i32) -> bool {
+        self.config.priorities.is_empty() || self.config.priorities.contains(&priority)
+    }
+
+    pub fn resolves_on_exit(&self) -> bool {
+        self.config.resolve_on_exit
+    }
+
+    /// Open an incident for the issue. Repeat reminders for the same issue
+    /// are folded into its open incident by PagerDuty.
+    pub async fn trigger(&self, issue: &Issue, summary: String) {
+        let event = PagerDutyEvent {
+            routing_key: self.config.routing_key.expose_secret(),
+            event_action: "trigger",
+            dedup_key: dedup_key(&issue.id),
+            payload: Some(PagerDutyPayload {
+                summary,
+                source: "linear-reminder",
+                severity: self.config.severity,
+                custom_details: issue,
+            }),
+        };
+        match self.send(&event).await {
+            Ok(()) => debug!(issue=?issue, "triggered pagerduty incident"),
+            Err(e) => warn!(issue=?issue, err=%e, "failed to trigger pagerduty incident"),
+        }
+    }
+
+    /// Resolve the issue's incident, if it has one. PagerDuty ignores resolves
+    /// for incidents that don't exist.
+    pub async fn resolve(&self, issue_id: &str) {
+        let event = PagerDutyEvent {
+            routing_key: self.config.routing_key.expose_secret(),
+            event_action: "resolve",
+            dedup_key: dedup_key(issue_id),
+            payload: None,
+        };
+        match self.send(&event).await {
+            Ok(()) => debug!(issue_id=%issue_id, "resolved pagerduty incident"),
+            Err(e) => warn!(issue_id=%issue_id, err=%e, "failed to resolve pagerduty incident"),
+        }
+    }
+
+    async fn send(&self, event: &PagerDutyEvent<'_>) -> reqwest::Result<()> {
+        self.http
+            .post(&self.config.url)
+            .json(event)
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// One incident per issue.
+fn dedup_key(issue_id: &str) -> String {
+    format!("linear-reminder/{issue_id}")
+}
@@ -0,0 +1,373 @@
+//! Reminder messages, rendered from the configured `message` template.
+//!
+//! Ref: <https://handlebarsjs.com/guide/>
+use std::{borrow::Cow, fmt};
+
+use chrono::TimeDelta;
+use handlebars::{Handlebars, RenderError, TemplateError};
+use rocket::serde::json::serde_json;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::{linear::EnrichedIssue, Issue, IssueData};
+
+const NAME: &str = "message";
+
+/// The issue fields available to the message template.
+#[derive(Debug, Serialize)]
+pub struct MessageContext<'a> {
+    identifier: &'a str,
+    title: &'a str,
+    url: Option<&'a str>,
+    assignee: Option<&'a str>,
+    /// Who moved the issue into the target status, if known.
+    actor: Option<&'a str>,
+    labels: Vec<&'a str>,
+    priority: i32,
+    /// How long the issue waits in the target status before being reminded,
+    /// e.g., `2days 4h`.
+    threshold: Option<String>,
+    /// How long the issue has actually been in the target status, which is
+    /// only known when the reminder is sent.
+    elapsed: Option<String>,
+    /// Only present for enriched templates.
+    #[serde(flatten)]
+    enriched: Option<EnrichedContext<'a>>,
+}
+
+/// The fields only available to enriched templates.
+#[derive(Debug, Default, Serialize)]
+struct EnrichedContext<'a> {
+    assignee_email: Option<&'a str>,
+    cycle: Option<&'a str>,
+    project: Option<&'a str>,
+}
+
+impl<'a> MessageContext<'a> {
+    /// Name whoever moved the issue into the target status, which is only
+    /// known from the webhook that queued it.
+    pub fn with_actor(mut self, actor: Option<&'a str>) -> Self {
+        self.actor = actor;
+        self
+    }
+
+    /// Say how long the issue waits before being reminded.
+    pub fn with_threshold(mut self, threshold: TimeDelta) -> Self {
+        self.threshold = Some(format_duration(threshold));
+        self
+    }
+
+    /// Say how long the issue has been waiting, when the reminder is sent.
+    pub fn with_elapsed(mut self, elapsed: TimeDelta) -> Self {
+        self.elapsed = Some(format_duration(elapsed));
+        self
+    }
+
+    /// Fill in the issue's current fields from Linear, for enriched templates.
+    /// If they couldn't be fetched, the enriched fields are left empty, since
+    /// a reminder is better than none.
+    pub fn enrich(mut self, issue: Option<&'a EnrichedIssue>) -> Self {
+        let Some(issue) = issue else {
+            self.enriched = Some(EnrichedContext::default());
+            return self;
+        };
+        self.url = Some(&issue.url);
+        self.assignee = issue.assignee.as_deref();
+        self.labels = issue.labels.iter().map(String::as_str).collect();
+        self.enriched = Some(EnrichedContext {
+            assignee_email: issue.assignee_email.as_deref(),
+            cycle: issue.cycle.as_deref(),
+            project: issue.project.as_deref(),
+        });
+        self
+    }
+}
+
+impl<'a> From<&'a IssueData> for MessageContext<'a> {
+    fn from(issue: &'a IssueData) -> Self {
+        MessageContext {
+            identifier: &issue.identifier,
+            title: &issue.title,
+            url: issue.url.as_deref(),
+            assignee: issue.assignee.as_ref().map(|a| a.name.as_str()),
+            actor: None,
+            labels: issue.labels.iter().map(|l| l.name.as_str()).collect(),
+            priority: issue.priority,
+            threshold: None,
+            elapsed: None,
+            enriched: None,
+        }
+    }
+}
+
+impl<'a> From<&'a Issue> for MessageContext<'a> {
+    fn from(issue: &'a Issue) -> Self {
+        MessageContext {
+            identifier: &issue.identifier,
+            title: &issue.title,
+            url: None,
+            assignee: None,
+            actor: issue.actor.as_deref(),
+            labels: Vec::new(),
+            priority: issue.priority,
+            threshold: None,
+            elapsed: None,
+            enriched: None,
+        }
+    }
+}
+
+/// A reminder message template, compiled once at startup.
+pub struct MessageTemplate {
+    source: String,
+    registry: Handlebars<'static>,
+    enriched: bool,
+    /// Whether the template uses `elapsed`.
+    elapsed: bool,
+}
+
+/// Why a message template was rejected at startup.
+#[derive(Debug)]
+pub enum InvalidTemplate {
+    Syntax(TemplateError),
+    Render(RenderError),
+    Empty,
+}
+
+impl fmt::Display for InvalidTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidTemplate::Syntax(e) => write!(f, "invalid message template: {e}"),
+            InvalidTemplate::Render(e) => write!(f, "failed to render message template: {e}"),
+            InvalidTemplate::Empty => write!(f, "message template is empty"),
+        }
+    }
+}
+
+impl std::error::Error for InvalidTemplate {}
+
+impl MessageTemplate {
+    /// Compile the template, and check that it renders against sample issue
+    /// data so that typos in field names are caught up front.
+    ///
+    /// Enriched templates may also use fields fetched from Linear when the
+    /// reminder is sent, so they're rendered then rather than when the issue
+    /// is queued. Likewise for templates using `elapsed`.
+    pub fn new(source: &str, enriched: bool) -> Result<Self, InvalidTemplate> {
+        // Linear rejects empty comments.
+        if is_blank(source) {
+            return Err(InvalidTemplate::Empty);
+        }
+        let mut registry = Handlebars::new();
+        registry.set_strict_mode(true);
+        registry.register_escape_fn(escape_graphql);
+        registry
+            .register_template_string(NAME, source)
+            .map_err(InvalidTemplate::Syntax)?;
+
+        let mut template = MessageTemplate {
+            source: source.to_string(),
+            registry,
+            enriched,
+            elapsed: false,
+        };
+        let sample = || MessageContext {
+            identifier: "ENG-123",
+            title: "Sample issue",
+            url: Some("https://linear.app/example/issue/ENG-123"),
+            assignee: Some("Sample User"),
+            actor: Some("Sample User"),
+            labels: vec!["Bug"],
+            priority: 2,
+            threshold: Some(format_duration(TimeDelta::days(1))),
+            elapsed: None,
+            enriched: enriched.then_some(EnrichedContext {
+                assignee_email: Some("sample@example.com"),
+                cycle: Some("Cycle 1"),
+                project: Some("Sample project"),
+            }),
+        };
+        let without_elapsed = template
+            .try_render(&sample())
+            .map_err(InvalidTemplate::Render)?;
+        // Handlebars can't tell us which fields a template uses, but if it
+        // renders differently with `elapsed`, it uses it.
+        let with_elapsed = template
+            .try_render(&sample().with_elapsed(TimeDelta::days(2)))
+            .map_err(InvalidTemplate::Render)?;
+        template.elapsed = with_elapsed != without_elapsed;
+        Ok(template)
+    }
+
+    pub fn is_enriched(&self) -> bool {
+        self.enriched
+    }
+
+    /// Whether the template can only be rendered when the reminder is sent,
+    /// rather than when the issue is queued.
+    pub fn renders_when_sent(&self) -> bool {
+        self.enriched || self.elapsed
+    }
+
+    /// Render the message for an issue, falling back to the raw template if
+    /// rendering fails, since a reminder is better than none.
+    pub fn render(&self, context: &MessageContext) -> String {
+        self.try_render(context).unwrap_or_else(|e| {
+            warn!(context=?context, err=%e, "failed to render message template");
+            self.source.clone()
+        })
+    }
+
+    fn try_render(&self, context: &MessageContext) -> Result<String, RenderError> {
+        self.registry.render(NAME, context)
+    }
+}
+
+/// A duration for people to read, to the minute, e.g., `2days 4h 30m`.
+fn format_duration(duration: TimeDelta) -> String {
+    let duration = duration.to_std().unwrap_or_default();
+    let duration = if duration.as_secs() < 60 {
+        std::time::Duration::from_secs(duration.as_secs())
+    } else {
+        std::time::Duration::from_secs(duration.as_secs() / 60 * 60)
+    };
+    humantime::format_duration(duration).to_string()
+}
+
+/// Whether a message, escaped for a GraphQL string, is only whitespace, e.g.,
+/// `\n\n`.
+pub fn is_blank(message: &str) -> bool {
+    unescape_graphql(message).trim().is_empty()
+}
+
+/// Escape values for the GraphQL string the message is embedded in, while
+/// leaving the template's own text (and its `\n` escapes) untouched.
+pub fn escape_graphql(value: &str) -> String {
+    let quoted = serde_json::to_string(value).expect("strings are always serializable");
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+/// Undo `escape_graphql`, e.g., to see the message as Linear will show it.
+/// Anything that isn't validly escaped is returned as is.
+///
+/// GraphQL strings share JSON's escapes.
+pub fn unescape_graphql(value: &str) -> Cow<'_, str> {
+    serde_json::from_str::<String>(&format!("\"{value}\"")).map_or(Cow::Borrowed(value), Cow::Owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn context<'a>(title: &'a str) -> MessageContext<'a> {
+        MessageContext {
+            identifier: "ENG-1",
+            title,
+            url: None,
+            assignee: None,
+            labels: Vec::new(),
+            priority: 0,
+            enriched: None,
+            actor: None,
+            threshold: None,
+            elapsed: None,
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        assert!(matches!(
+            MessageTemplate::new(r"\n \n", false),
+            Err(InvalidTemplate::Empty)
+        ));
+        assert!(matches!(
+            MessageTemplate::new("{{#if title}}", false),
+            Err(InvalidTemplate::Syntax(_))
+        ));
+        assert!(matches!(
+            MessageTemplate::new("{{titel}}", false),
+            Err(InvalidTemplate::Render(_))
+        ));
+        assert!(matches!(
+            MessageTemplate::new("{{project}}", false),
+            Err(InvalidTemplate::Render(_))
+        ));
+        assert!(MessageTemplate::new("{{project}}", true).is_ok());
+    }
+
+    #[test]
+    fn escapes_values_but_not_the_template() {
+        let template = MessageTemplate::new(r#"{{identifier}}:\n{{title}}"#, false).unwrap();
+        assert_eq!(
+            template.render(&context("Say \"hi\"\n")),
+            r#"ENG-1:\nSay \"hi\"\n"#
+        );
+    }
+
+    #[test]
+    fn names_the_actor_if_known() {
+        let template = MessageTemplate::new(
+            "{{identifier}}{{#if actor}}, moved by {{actor}}{{/if}}",
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            template.render(&context("title").with_actor(Some("Ada"))),
+            "ENG-1, moved by Ada"
+        );
+        assert_eq!(template.render(&context("title")), "ENG-1");
+    }
+
+    #[test]
+    fn renders_enriched_fields() {
+        let template =
+            MessageTemplate::new("{{identifier}} in {{project}} for {{assignee}}", true).unwrap();
+        let issue = EnrichedIssue {
+            url: "https://linear.app/team/issue/ENG-1".to_string(),
+            assignee: Some("Ada".to_string()),
+            assignee_email: Some("ada@example.com".to_string()),
+            labels: vec!["bug".to_string()],
+            cycle: None,
+            project: Some("Launch".to_string()),
+        };
+        assert_eq!(
+            template.render(&context("title").enrich(Some(&issue))),
+            "ENG-1 in Launch for Ada"
+        );
+        // A failed fetch still renders, just without the enriched fields.
+        assert_eq!(
+            template.render(&context("title").enrich(None)),
+            "ENG-1 in  for "
+        );
+    }
+
+    #[test]
+    fn detects_templates_rendered_when_sent() {
+        let template = MessageTemplate::new("{{title}}", false).unwrap();
+        assert!(!template.renders_when_sent());
+        let template = MessageTemplate::new("Waiting {{elapsed}}", false).unwrap();
+        assert!(template.renders_when_sent());
+        let template = MessageTemplate::new("{{title}}", true).unwrap();
+        assert!(template.renders_when_sent());
+    }
+
+    #[test]
+    fn detects_blank_messages() {
+        assert!(is_blank(""));
+        assert!(is_blank(r"\n\t "));
+        assert!(!is_blank(r"\nhi"));
+        assert!(!is_blank(r#"unbalanced ""#));
+    }
+
+    #[test]
+    fn formats_durations_to_the_minute() {
+        assert_eq!(format_duration(TimeDelta::seconds(42)), "42s");
+        assert_eq!(
+            format_duration(TimeDelta::seconds(2 * 86400 + 3 * 3600 + 59)),
+            "2days 3h"
+        );
+        assert_eq!(format_duration(TimeDelta::seconds(-5)), "0s");
+    }
+}